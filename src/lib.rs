@@ -0,0 +1,1376 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use phf::phf_map;
+use thiserror::Error;
+
+type Result<T> = std::result::Result<T, EvalError>;
+
+/// A value that can live on the data stack.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Bool(bool),
+    Str(String),
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "Int",
+            Value::Bool(_) => "Bool",
+            Value::Str(_) => "Str",
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(n) => write!(f, "{n}"),
+            Value::Bool(b) => write!(f, "{}", if *b { "true" } else { "false" }),
+            Value::Str(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+/// Errors `Evaluator::process` can fail with, in place of stringly-typed
+/// `anyhow` failures, so callers can match on what actually went wrong.
+#[derive(Error, Debug, PartialEq)]
+pub enum EvalError {
+    #[error("too few arguments for '{word}'")]
+    StackUnderflow { word: String },
+    #[error("'{word}' expected {expected}, got {got}")]
+    TypeMismatch {
+        word: String,
+        expected: String,
+        got: String,
+    },
+    #[error("attempt to divide by zero")]
+    DivisionByZero,
+    #[error("unknown word: '{0}'")]
+    UnknownWord(String),
+    #[error("cannot redefine a number as a word")]
+    CannotRedefineNumber,
+    /// Malformed `if`/`else`/`then`, `begin`/`until`, `do`/`loop`, an
+    /// unterminated `:` definition or string literal, or a `:` with no name;
+    /// all of these are caught at compile time. `i` used outside a `do` loop
+    /// is also reported with this variant, but only when it runs.
+    #[error("{0}")]
+    Syntax(String),
+}
+
+/// Arithmetic and stack-shuffling primitives the VM can execute directly.
+#[derive(Clone, Debug)]
+pub enum Keyword {
+    Plus,
+    Minus,
+    Mul,
+    Div,
+    Over,
+    Swap,
+    Dup,
+    Drop,
+    Lt,
+    Gt,
+    Eq,
+    And,
+    Or,
+    Invert,
+    Concat,
+    Print,
+    Rot,
+    Nip,
+    Tuck,
+    TwoDup,
+    Negate,
+    Abs,
+    Mod,
+    Min,
+    Max,
+}
+
+impl Keyword {
+    /// The token this primitive compiles from, used to label its errors.
+    fn name(&self) -> &'static str {
+        match self {
+            Keyword::Plus => "+",
+            Keyword::Minus => "-",
+            Keyword::Mul => "*",
+            Keyword::Div => "/",
+            Keyword::Over => "over",
+            Keyword::Swap => "swap",
+            Keyword::Dup => "dup",
+            Keyword::Drop => "drop",
+            Keyword::Lt => "<",
+            Keyword::Gt => ">",
+            Keyword::Eq => "=",
+            Keyword::And => "and",
+            Keyword::Or => "or",
+            Keyword::Invert => "invert",
+            Keyword::Concat => "concat",
+            Keyword::Print => ".",
+            Keyword::Rot => "rot",
+            Keyword::Nip => "nip",
+            Keyword::Tuck => "tuck",
+            Keyword::TwoDup => "2dup",
+            Keyword::Negate => "negate",
+            Keyword::Abs => "abs",
+            Keyword::Mod => "mod",
+            Keyword::Min => "min",
+            Keyword::Max => "max",
+        }
+    }
+}
+
+/// Primitives available in every `Evaluator`, prelude or not.
+static CORE_KEYWORDS: phf::Map<&'static str, Keyword> = phf_map! {
+    "+" => Keyword::Plus,
+    "-" => Keyword::Minus,
+    "*" => Keyword::Mul,
+    "/" => Keyword::Div,
+    "dup" => Keyword::Dup,
+    "over" => Keyword::Over,
+    "drop" => Keyword::Drop,
+    "swap" => Keyword::Swap,
+    "<" => Keyword::Lt,
+    ">" => Keyword::Gt,
+    "=" => Keyword::Eq,
+    "and" => Keyword::And,
+    "or" => Keyword::Or,
+    "invert" => Keyword::Invert,
+    "concat" => Keyword::Concat,
+    "." => Keyword::Print,
+};
+
+/// Native "library" primitives seeded by the prelude (see `PRELUDE_SRC` for
+/// its Forth-source counterparts). Only resolvable when `Evaluator` was
+/// built with `with_prelude(true)`, so `bare()` gets just the core above.
+static PRELUDE_KEYWORDS: phf::Map<&'static str, Keyword> = phf_map! {
+    "rot" => Keyword::Rot,
+    "nip" => Keyword::Nip,
+    "tuck" => Keyword::Tuck,
+    "2dup" => Keyword::TwoDup,
+    "negate" => Keyword::Negate,
+    "abs" => Keyword::Abs,
+    "mod" => Keyword::Mod,
+    "min" => Keyword::Min,
+    "max" => Keyword::Max,
+};
+
+/// A single instruction in the flat bytecode a definition (or a top-level
+/// line) compiles down to.
+#[derive(Clone, Debug)]
+pub enum Op {
+    /// Push a literal value onto the data stack.
+    Push(Value),
+    /// Run a built-in arithmetic/stack primitive.
+    Prim(Keyword),
+    /// Invoke another compiled definition by its index into `defs`.
+    Call(usize),
+    /// Jump unconditionally to an absolute instruction offset.
+    Jump(usize),
+    /// Pop the data stack; jump to an absolute offset if it was false.
+    /// `word` names the construct that compiled this jump (`"if"` or
+    /// `"until"`), so a type error on the popped flag names the right word.
+    JumpIfZero(usize, &'static str),
+    /// Pop `start` then `limit` off the data stack and start a `DO` loop.
+    Do,
+    /// End of a `DO` body: advance the loop index, looping back to the
+    /// given offset while it's still under the limit.
+    Loop(usize),
+    /// Push the innermost `DO` loop's current index.
+    LoopIndex,
+}
+
+/// Tracks an open control-flow construct while compiling a definition, so
+/// its closing word knows which placeholder jump(s) to back-patch (`IF`/
+/// `ELSE`/`THEN`), or where to jump back to (`BEGIN`/`UNTIL`, `DO`/`LOOP`).
+enum CtrlFrame {
+    If(usize),
+    IfElse(usize),
+    Begin(usize),
+    Do(usize),
+}
+
+fn pop(stack: &mut Vec<Value>, word: &str) -> Result<Value> {
+    stack.pop().ok_or_else(|| EvalError::StackUnderflow {
+        word: word.to_string(),
+    })
+}
+
+fn pop_int(stack: &mut Vec<Value>, word: &str) -> Result<i64> {
+    match pop(stack, word)? {
+        Value::Int(n) => Ok(n),
+        other => Err(EvalError::TypeMismatch {
+            word: word.to_string(),
+            expected: "Int".into(),
+            got: other.type_name().into(),
+        }),
+    }
+}
+
+fn pop_bool(stack: &mut Vec<Value>, word: &str) -> Result<bool> {
+    match pop(stack, word)? {
+        Value::Bool(b) => Ok(b),
+        other => Err(EvalError::TypeMismatch {
+            word: word.to_string(),
+            expected: "Bool".into(),
+            got: other.type_name().into(),
+        }),
+    }
+}
+
+fn pop_str(stack: &mut Vec<Value>, word: &str) -> Result<String> {
+    match pop(stack, word)? {
+        Value::Str(s) => Ok(s),
+        other => Err(EvalError::TypeMismatch {
+            word: word.to_string(),
+            expected: "Str".into(),
+            got: other.type_name().into(),
+        }),
+    }
+}
+
+pub struct Evaluator {
+    /// Compiled definitions, looked up by index from a call frame.
+    defs: Vec<Vec<Op>>,
+    /// Name -> index into `defs` for the word currently bound to that name.
+    def_names: HashMap<String, usize>,
+    /// The data stack, carried across calls to `process` so a REPL-style
+    /// caller sees it accumulate line by line.
+    stack: Vec<Value>,
+    /// Text written by `.`, buffered here rather than printed directly so
+    /// embedders without a terminal (e.g. the wasm `Session`) can read it
+    /// back via `take_output` instead of losing it to a no-op `println!`.
+    output: String,
+    /// Whether the native words in `PRELUDE_KEYWORDS` resolve (see `bare`).
+    native_prelude: bool,
+}
+
+/// Forth-source words seeded into every prelude-carrying `Evaluator`,
+/// compiled through the ordinary `:` pipeline so users can override them
+/// exactly like they override `swap` today.
+const PRELUDE_SRC: &str = "\
+    : 2drop drop drop ;\n\
+    : square dup * ;\n\
+";
+
+impl Evaluator {
+    /// A new evaluator seeded with the standard prelude of shuffle and
+    /// arithmetic words (see [`Evaluator::bare`] to opt out).
+    pub fn new() -> Evaluator {
+        Evaluator::with_prelude(true)
+    }
+
+    /// A new evaluator with no prelude loaded: only the core primitives in
+    /// `CORE_KEYWORDS` are available.
+    pub fn bare() -> Evaluator {
+        Evaluator::with_prelude(false)
+    }
+
+    pub fn with_prelude(load_prelude: bool) -> Evaluator {
+        let mut ev = Evaluator {
+            defs: Vec::new(),
+            def_names: HashMap::new(),
+            stack: Vec::new(),
+            output: String::new(),
+            native_prelude: load_prelude,
+        };
+        if load_prelude {
+            ev.process(PRELUDE_SRC)
+                .expect("built-in prelude must compile");
+        }
+        ev
+    }
+
+    /// The current contents of the data stack, bottom first.
+    pub fn stack(&self) -> &[Value] {
+        &self.stack
+    }
+
+    /// Drain and return the text written by `.` since the last call to
+    /// `take_output` (or since the evaluator was created).
+    pub fn take_output(&mut self) -> String {
+        std::mem::take(&mut self.output)
+    }
+
+    /// Compile and run one line, leaving its effect on the data stack in
+    /// place for the next call and returning the stack's new contents. A
+    /// line that fails to compile or run leaves the stack exactly as it was
+    /// before the call, so a caller like the REPL can recover without
+    /// losing prior state.
+    pub fn process(&mut self, row: impl AsRef<str>) -> Result<Vec<Value>> {
+        let mut tokens = row.as_ref().split_whitespace();
+        let ops = self.compile_block(&mut tokens, None)?;
+
+        // Run the line's own code as a throwaway definition so the VM only
+        // ever has to deal with one kind of call target: an index into `defs`.
+        let entry = self.defs.len();
+        self.defs.push(ops);
+
+        let mut working = self.stack.clone();
+        let result = self.run(entry, &mut working);
+        self.defs.truncate(entry);
+        result?;
+
+        self.stack = working;
+        Ok(self.stack.clone())
+    }
+
+    /// Compile tokens into a flat op sequence until `stop_at` is seen (or,
+    /// if `stop_at` is `None`, until the tokens run out), registering any
+    /// `: name ... ;` definitions and resolving `IF`/`ELSE`/`THEN` jumps
+    /// along the way instead of emitting code for them directly.
+    fn compile_block<'a>(
+        &mut self,
+        tokens: &mut impl Iterator<Item = &'a str>,
+        stop_at: Option<&str>,
+    ) -> Result<Vec<Op>> {
+        let mut ops = Vec::new();
+        let mut ctrl: Vec<CtrlFrame> = Vec::new();
+
+        loop {
+            let Some(tok) = tokens.next() else {
+                if stop_at.is_some() {
+                    return Err(EvalError::Syntax("unterminated definition".into()));
+                }
+                break;
+            };
+            if Some(tok) == stop_at {
+                break;
+            }
+
+            match tok {
+                ":" => self.compile_definition(tokens)?,
+                "s\"" => ops.push(Op::Push(Value::Str(read_string_literal(tokens)?))),
+                "if" => {
+                    ops.push(Op::JumpIfZero(usize::MAX, "if"));
+                    ctrl.push(CtrlFrame::If(ops.len() - 1));
+                }
+                "else" => {
+                    let Some(CtrlFrame::If(if_pos)) = ctrl.pop() else {
+                        return Err(EvalError::Syntax("'else' without a matching 'if'".into()));
+                    };
+                    ops.push(Op::Jump(usize::MAX));
+                    let else_pos = ops.len() - 1;
+                    ops[if_pos] = Op::JumpIfZero(ops.len(), "if");
+                    ctrl.push(CtrlFrame::IfElse(else_pos));
+                }
+                "then" => match ctrl.pop() {
+                    Some(CtrlFrame::If(if_pos)) => ops[if_pos] = Op::JumpIfZero(ops.len(), "if"),
+                    Some(CtrlFrame::IfElse(else_pos)) => ops[else_pos] = Op::Jump(ops.len()),
+                    _ => return Err(EvalError::Syntax("'then' without a matching 'if'".into())),
+                },
+                "begin" => ctrl.push(CtrlFrame::Begin(ops.len())),
+                "until" => {
+                    let Some(CtrlFrame::Begin(target)) = ctrl.pop() else {
+                        return Err(EvalError::Syntax(
+                            "'until' without a matching 'begin'".into(),
+                        ));
+                    };
+                    ops.push(Op::JumpIfZero(target, "until"));
+                }
+                "do" => {
+                    ops.push(Op::Do);
+                    ctrl.push(CtrlFrame::Do(ops.len()));
+                }
+                "loop" => {
+                    let Some(CtrlFrame::Do(target)) = ctrl.pop() else {
+                        return Err(EvalError::Syntax("'loop' without a matching 'do'".into()));
+                    };
+                    ops.push(Op::Loop(target));
+                }
+                "i" => ops.push(Op::LoopIndex),
+                _ => ops.push(self.resolve(tok)?),
+            }
+        }
+
+        if !ctrl.is_empty() {
+            return Err(EvalError::Syntax(
+                "unbalanced control flow: an 'if', 'begin' or 'do' is left open".into(),
+            ));
+        }
+        Ok(ops)
+    }
+
+    fn compile_definition<'a>(
+        &mut self,
+        tokens: &mut impl Iterator<Item = &'a str>,
+    ) -> Result<()> {
+        let name = tokens
+            .next()
+            .ok_or_else(|| EvalError::Syntax("expected a name after ':'".into()))?;
+        if name.parse::<i64>().is_ok() {
+            return Err(EvalError::CannotRedefineNumber);
+        }
+
+        let body = self.compile_block(tokens, Some(";"))?;
+
+        // Pointing the name at a fresh index (rather than overwriting the old
+        // one in place) means a definition that refers to the word it's
+        // shadowing, e.g. `: foo foo 1 + ;`, keeps calling the previous
+        // binding: it was resolved to that index while compiling this body.
+        let idx = self.defs.len();
+        self.defs.push(body);
+        self.def_names.insert(name.to_lowercase(), idx);
+        Ok(())
+    }
+
+    /// Resolve a single word to the op it compiles to, at compile time.
+    /// Word lookup is case-insensitive, so `DUP`, `Dup`, and `dup` all
+    /// resolve to the same primitive (or user definition).
+    fn resolve(&self, word: &str) -> Result<Op> {
+        let key = word.to_lowercase();
+        if let Some(&idx) = self.def_names.get(&key) {
+            return Ok(Op::Call(idx));
+        }
+        if let Some(kw) = CORE_KEYWORDS.get(key.as_str()) {
+            return Ok(Op::Prim(kw.clone()));
+        }
+        if self.native_prelude {
+            if let Some(kw) = PRELUDE_KEYWORDS.get(key.as_str()) {
+                return Ok(Op::Prim(kw.clone()));
+            }
+        }
+        match key.as_str() {
+            "true" => return Ok(Op::Push(Value::Bool(true))),
+            "false" => return Ok(Op::Push(Value::Bool(false))),
+            _ => {}
+        }
+        word.parse::<i64>()
+            .map(|n| Op::Push(Value::Int(n)))
+            .map_err(|_| EvalError::UnknownWord(word.to_string()))
+    }
+
+    /// Execute the definition at `entry`, using an explicit call/return
+    /// frame stack instead of recursing, so deep or self-referential
+    /// definitions don't blow the Rust call stack.
+    fn run(&mut self, entry: usize, stack: &mut Vec<Value>) -> Result<()> {
+        let mut frames: Vec<(usize, usize)> = vec![(entry, 0)];
+        // (index, limit) per active DO loop, kept apart from the data stack
+        // and the call-frame stack so nested loops nest correctly.
+        let mut loop_stack: Vec<(i64, i64)> = Vec::new();
+
+        while let Some(&(def_idx, ip)) = frames.last() {
+            let Some(op) = self.defs[def_idx].get(ip).cloned() else {
+                frames.pop();
+                continue;
+            };
+
+            match op {
+                Op::Push(v) => {
+                    stack.push(v);
+                    frames.last_mut().unwrap().1 += 1;
+                }
+                Op::Prim(kw) => {
+                    self.evaluate(stack, kw)?;
+                    frames.last_mut().unwrap().1 += 1;
+                }
+                Op::Call(idx) => {
+                    frames.last_mut().unwrap().1 += 1;
+                    frames.push((idx, 0));
+                }
+                Op::Jump(target) => frames.last_mut().unwrap().1 = target,
+                Op::JumpIfZero(target, word) => {
+                    let flag = pop_bool(stack, word)?;
+                    frames.last_mut().unwrap().1 = if flag { ip + 1 } else { target };
+                }
+                Op::Do => {
+                    let index = pop_int(stack, "do")?;
+                    let limit = pop_int(stack, "do")?;
+                    loop_stack.push((index, limit));
+                    frames.last_mut().unwrap().1 += 1;
+                }
+                Op::Loop(target) => {
+                    let Some((index, limit)) = loop_stack.last_mut() else {
+                        return Err(EvalError::Syntax("'loop' without a matching 'do'".into()));
+                    };
+                    *index += 1;
+                    if *index < *limit {
+                        frames.last_mut().unwrap().1 = target;
+                    } else {
+                        loop_stack.pop();
+                        frames.last_mut().unwrap().1 = ip + 1;
+                    }
+                }
+                Op::LoopIndex => {
+                    let Some(&(index, _)) = loop_stack.last() else {
+                        return Err(EvalError::Syntax("'i' used outside of a 'do' loop".into()));
+                    };
+                    stack.push(Value::Int(index));
+                    frames.last_mut().unwrap().1 += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn evaluate(&mut self, stack: &mut Vec<Value>, keyword: Keyword) -> Result<()> {
+        let word = keyword.name();
+        match keyword {
+            Keyword::Drop => {
+                pop(stack, word)?;
+            }
+            Keyword::Dup => {
+                let v = pop(stack, word)?;
+                stack.push(v.clone());
+                stack.push(v);
+            }
+            Keyword::Over => {
+                let b = pop(stack, word)?;
+                let a = pop(stack, word)?;
+                stack.push(a.clone());
+                stack.push(b);
+                stack.push(a);
+            }
+            Keyword::Swap => {
+                let b = pop(stack, word)?;
+                let a = pop(stack, word)?;
+                stack.push(b);
+                stack.push(a);
+            }
+            Keyword::Rot => {
+                let c = pop(stack, word)?;
+                let b = pop(stack, word)?;
+                let a = pop(stack, word)?;
+                stack.push(b);
+                stack.push(c);
+                stack.push(a);
+            }
+            Keyword::Nip => {
+                let b = pop(stack, word)?;
+                pop(stack, word)?;
+                stack.push(b);
+            }
+            Keyword::Tuck => {
+                let b = pop(stack, word)?;
+                let a = pop(stack, word)?;
+                stack.push(b.clone());
+                stack.push(a);
+                stack.push(b);
+            }
+            Keyword::TwoDup => {
+                let b = pop(stack, word)?;
+                let a = pop(stack, word)?;
+                stack.push(a.clone());
+                stack.push(b.clone());
+                stack.push(a);
+                stack.push(b);
+            }
+            Keyword::Eq => {
+                let b = pop(stack, word)?;
+                let a = pop(stack, word)?;
+                stack.push(Value::Bool(a == b));
+            }
+            Keyword::Invert => {
+                let a = pop_bool(stack, word)?;
+                stack.push(Value::Bool(!a));
+            }
+            Keyword::And => {
+                let b = pop_bool(stack, word)?;
+                let a = pop_bool(stack, word)?;
+                stack.push(Value::Bool(a && b));
+            }
+            Keyword::Or => {
+                let b = pop_bool(stack, word)?;
+                let a = pop_bool(stack, word)?;
+                stack.push(Value::Bool(a || b));
+            }
+            Keyword::Concat => {
+                let b = pop_str(stack, word)?;
+                let a = pop_str(stack, word)?;
+                stack.push(Value::Str(a + &b));
+            }
+            Keyword::Print => {
+                let v = pop(stack, word)?;
+                self.output.push_str(&v.to_string());
+                self.output.push('\n');
+            }
+            Keyword::Lt => {
+                let b = pop_int(stack, word)?;
+                let a = pop_int(stack, word)?;
+                stack.push(Value::Bool(a < b));
+            }
+            Keyword::Gt => {
+                let b = pop_int(stack, word)?;
+                let a = pop_int(stack, word)?;
+                stack.push(Value::Bool(a > b));
+            }
+            Keyword::Plus => {
+                let b = pop_int(stack, word)?;
+                let a = pop_int(stack, word)?;
+                stack.push(Value::Int(a + b));
+            }
+            Keyword::Minus => {
+                let b = pop_int(stack, word)?;
+                let a = pop_int(stack, word)?;
+                stack.push(Value::Int(a - b));
+            }
+            Keyword::Mul => {
+                let b = pop_int(stack, word)?;
+                let a = pop_int(stack, word)?;
+                stack.push(Value::Int(a * b));
+            }
+            Keyword::Div => {
+                let b = pop_int(stack, word)?;
+                let a = pop_int(stack, word)?;
+                if b == 0 {
+                    return Err(EvalError::DivisionByZero);
+                }
+                stack.push(Value::Int(a / b));
+            }
+            Keyword::Mod => {
+                let b = pop_int(stack, word)?;
+                let a = pop_int(stack, word)?;
+                if b == 0 {
+                    return Err(EvalError::DivisionByZero);
+                }
+                stack.push(Value::Int(a % b));
+            }
+            Keyword::Negate => {
+                let a = pop_int(stack, word)?;
+                stack.push(Value::Int(-a));
+            }
+            Keyword::Abs => {
+                let a = pop_int(stack, word)?;
+                stack.push(Value::Int(a.abs()));
+            }
+            Keyword::Min => {
+                let b = pop_int(stack, word)?;
+                let a = pop_int(stack, word)?;
+                stack.push(Value::Int(a.min(b)));
+            }
+            Keyword::Max => {
+                let b = pop_int(stack, word)?;
+                let a = pop_int(stack, word)?;
+                stack.push(Value::Int(a.max(b)));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Read the body of a `s" ... "` string literal: tokens up to and including
+/// the one ending in `"`, rejoined with single spaces. There are no escapes:
+/// any token ending in `"` closes the literal.
+fn read_string_literal<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Result<String> {
+    let mut parts = Vec::new();
+    loop {
+        let Some(tok) = tokens.next() else {
+            return Err(EvalError::Syntax("unterminated string literal".into()));
+        };
+        match tok.strip_suffix('"') {
+            Some(rest) => {
+                if !rest.is_empty() {
+                    parts.push(rest);
+                }
+                break;
+            }
+            None => parts.push(tok),
+        }
+    }
+    Ok(parts.join(" "))
+}
+
+/// Thin WebAssembly binding around `Evaluator`, so the same interpreter
+/// backs both the terminal REPL and an in-browser playground.
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
+
+#[cfg(test)]
+mod test {
+    use crate::{EvalError, Evaluator, Value};
+
+    #[test]
+    fn test_num() {
+        let mut ev = Evaluator::new();
+        let stack = ev.process("10");
+        assert_eq!(vec![Value::Int(10)], stack.unwrap());
+
+        let mut ev = Evaluator::new();
+        let stack = ev.process("1 2 3");
+        assert_eq!(
+            vec![Value::Int(1), Value::Int(2), Value::Int(3)],
+            stack.unwrap()
+        );
+
+        let mut ev = Evaluator::new();
+        let stack = ev.process("1 3 -2");
+        assert_eq!(
+            vec![Value::Int(1), Value::Int(3), Value::Int(-2)],
+            stack.unwrap()
+        );
+    }
+
+    #[test]
+    fn test_plus() {
+        let mut ev = Evaluator::new();
+        let stack = ev.process("1 2 +");
+        assert_eq!(vec![Value::Int(3)], stack.unwrap());
+
+        let mut ev = Evaluator::new();
+        let e = ev.process("2 +").unwrap_err();
+        assert_eq!(
+            e,
+            EvalError::StackUnderflow {
+                word: "+".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_minus() {
+        let mut ev = Evaluator::new();
+        let stack = ev.process("1 2 -");
+        assert_eq!(vec![Value::Int(-1)], stack.unwrap());
+
+        let mut ev = Evaluator::new();
+        let stack = ev.process("10 2 -");
+        assert_eq!(vec![Value::Int(8)], stack.unwrap());
+    }
+
+    #[test]
+    fn test_mul() {
+        let mut ev = Evaluator::new();
+        let stack = ev.process("3 2 *");
+        assert_eq!(vec![Value::Int(6)], stack.unwrap());
+    }
+
+    #[test]
+    fn test_div() {
+        let mut ev = Evaluator::new();
+        let stack = ev.process("3 9 /");
+        assert_eq!(vec![Value::Int(0)], stack.unwrap());
+
+        let mut ev = Evaluator::new();
+        let stack = ev.process("9 3 /");
+        assert_eq!(vec![Value::Int(3)], stack.unwrap());
+
+        let e = ev.process("9 0 /").unwrap_err();
+        assert_eq!(e, EvalError::DivisionByZero);
+    }
+
+    #[test]
+    fn test_dup() {
+        let mut ev = Evaluator::new();
+        let stack = ev.process("3 dup");
+        assert_eq!(vec![Value::Int(3), Value::Int(3)], stack.unwrap());
+
+        let mut ev = Evaluator::new();
+        let stack = ev.process("9 3 dup");
+        assert_eq!(
+            vec![Value::Int(9), Value::Int(3), Value::Int(3)],
+            stack.unwrap()
+        );
+    }
+
+    #[test]
+    fn test_over() {
+        let mut ev = Evaluator::new();
+        let stack = ev.process("1 3 over");
+        assert_eq!(
+            vec![Value::Int(1), Value::Int(3), Value::Int(1)],
+            stack.unwrap()
+        );
+
+        let mut ev = Evaluator::new();
+        let stack = ev.process("1 2 3 over");
+        assert_eq!(
+            vec![Value::Int(1), Value::Int(2), Value::Int(3), Value::Int(2)],
+            stack.unwrap()
+        );
+
+        let mut ev = Evaluator::new();
+        let e = ev.process("9 over").unwrap_err();
+        assert_eq!(
+            e,
+            EvalError::StackUnderflow {
+                word: "over".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_swap() {
+        let mut ev = Evaluator::new();
+        let stack = ev.process("1 3 swap");
+        assert_eq!(vec![Value::Int(3), Value::Int(1)], stack.unwrap());
+
+        let mut ev = Evaluator::new();
+        let stack = ev.process("1 2 3 swap");
+        assert_eq!(
+            vec![Value::Int(1), Value::Int(3), Value::Int(2)],
+            stack.unwrap()
+        );
+
+        let mut ev = Evaluator::new();
+        let e = ev.process("9 swap").unwrap_err();
+        assert_eq!(
+            e,
+            EvalError::StackUnderflow {
+                word: "swap".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_strings() {
+        let mut ev = Evaluator::new();
+        let stack = ev.process(r#"s" hello" s" world" concat"#);
+        assert_eq!(vec![Value::Str("helloworld".to_string())], stack.unwrap());
+
+        let e = ev.process("1 concat").unwrap_err();
+        assert_eq!(
+            e,
+            EvalError::TypeMismatch {
+                word: "concat".to_string(),
+                expected: "Str".to_string(),
+                got: "Int".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_print_buffers_output() {
+        let mut ev = Evaluator::new();
+        ev.process("1 . 2 .").unwrap();
+        assert_eq!("1\n2\n", ev.take_output());
+
+        // Draining leaves nothing behind for the next line to pick up.
+        assert_eq!("", ev.take_output());
+        ev.process("3 .").unwrap();
+        assert_eq!("3\n", ev.take_output());
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        let mut ev = Evaluator::new();
+        let e = ev.process(r#"1 s" two" +"#).unwrap_err();
+        assert_eq!(
+            e,
+            EvalError::TypeMismatch {
+                word: "+".to_string(),
+                expected: "Int".to_string(),
+                got: "Str".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_until_type_mismatch_names_until() {
+        // `until` shares its compiled JumpIfZero with `if`; a bad flag here
+        // must be blamed on `until`, not `if`.
+        let mut ev = Evaluator::new();
+        ev.process(": bad begin 1 until ;").unwrap();
+        let e = ev.process("bad").unwrap_err();
+        assert_eq!(
+            e,
+            EvalError::TypeMismatch {
+                word: "until".to_string(),
+                expected: "Bool".to_string(),
+                got: "Int".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_bare_has_no_prelude() {
+        let mut ev = Evaluator::bare();
+        let e = ev.process("3 square").unwrap_err();
+        assert_eq!(e, EvalError::UnknownWord("square".to_string()));
+
+        // Native prelude words are gone too, not just the Forth-source ones.
+        let mut ev = Evaluator::bare();
+        let e = ev.process("1 2 3 rot").unwrap_err();
+        assert_eq!(e, EvalError::UnknownWord("rot".to_string()));
+
+        let mut ev = Evaluator::bare();
+        let e = ev.process("5 negate").unwrap_err();
+        assert_eq!(e, EvalError::UnknownWord("negate".to_string()));
+
+        // Core primitives are still there.
+        let mut ev = Evaluator::bare();
+        let stack = ev.process("1 2 +");
+        assert_eq!(vec![Value::Int(3)], stack.unwrap());
+    }
+
+    #[test]
+    fn test_big() {
+        struct Case {
+            description: &'static str,
+            input: &'static [&'static str],
+            expected: Vec<Value>,
+            is_err: bool,
+        }
+        let cases = vec![
+            Case {
+                description: "push numbers",
+                input: &["1 2 3 4 5"],
+                expected: vec![
+                    Value::Int(1),
+                    Value::Int(2),
+                    Value::Int(3),
+                    Value::Int(4),
+                    Value::Int(5),
+                ],
+                is_err: false,
+            },
+            Case {
+                description: "add",
+                input: &["1 2 +"],
+                expected: vec![Value::Int(3)],
+                is_err: false,
+            },
+            Case {
+                description: "nothing to add",
+                input: &["+"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "add arity",
+                input: &["1 +"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "sub",
+                input: &["3 4 -"],
+                expected: vec![Value::Int(-1)],
+                is_err: false,
+            },
+            Case {
+                description: "nothing to sub",
+                input: &["-"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "sub arity",
+                input: &["1 -"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "mul",
+                input: &["2 4 *"],
+                expected: vec![Value::Int(8)],
+                is_err: false,
+            },
+            Case {
+                description: "nothing to mul",
+                input: &["*"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "mul arity",
+                input: &["1 *"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "div",
+                input: &["12 3 /"],
+                expected: vec![Value::Int(4)],
+                is_err: false,
+            },
+            Case {
+                description: "integer division",
+                input: &["8 3 /"],
+                expected: vec![Value::Int(2)],
+                is_err: false,
+            },
+            Case {
+                description: "division by zero",
+                input: &["4 0 /"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "nothing to div",
+                input: &["/"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "div arity",
+                input: &["1 /"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "add sub",
+                input: &["1 2 + 4 -"],
+                expected: vec![Value::Int(-1)],
+                is_err: false,
+            },
+            Case {
+                description: "mul div",
+                input: &["2 4 * 3 /"],
+                expected: vec![Value::Int(2)],
+                is_err: false,
+            },
+            Case {
+                description: "dup",
+                input: &["1 dup"],
+                expected: vec![Value::Int(1), Value::Int(1)],
+                is_err: false,
+            },
+            Case {
+                description: "dup top",
+                input: &["1 2 dup"],
+                expected: vec![Value::Int(1), Value::Int(2), Value::Int(2)],
+                is_err: false,
+            },
+            Case {
+                description: "nothing to dup",
+                input: &["dup"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "drop",
+                input: &["1 drop"],
+                expected: vec![],
+                is_err: false,
+            },
+            Case {
+                description: "drop top",
+                input: &["1 2 drop"],
+                expected: vec![Value::Int(1)],
+                is_err: false,
+            },
+            Case {
+                description: "nothing to drop",
+                input: &["drop"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "swap",
+                input: &["1 2 swap"],
+                expected: vec![Value::Int(2), Value::Int(1)],
+                is_err: false,
+            },
+            Case {
+                description: "swap top",
+                input: &["1 2 3 swap"],
+                expected: vec![Value::Int(1), Value::Int(3), Value::Int(2)],
+                is_err: false,
+            },
+            Case {
+                description: "nothing to swap",
+                input: &["swap"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "swap arity",
+                input: &["1 swap"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "over",
+                input: &["1 2 over"],
+                expected: vec![Value::Int(1), Value::Int(2), Value::Int(1)],
+                is_err: false,
+            },
+            Case {
+                description: "over2",
+                input: &["1 2 3 over"],
+                expected: vec![Value::Int(1), Value::Int(2), Value::Int(3), Value::Int(2)],
+                is_err: false,
+            },
+            Case {
+                description: "nothing to over",
+                input: &["over"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "over arity",
+                input: &["1 over"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "user-defined",
+                input: &[": dup-twice dup dup ;", "1 dup-twice"],
+                expected: vec![Value::Int(1), Value::Int(1), Value::Int(1)],
+                is_err: false,
+            },
+            Case {
+                description: "user-defined order",
+                input: &[": countup 1 2 3 ;", "countup"],
+                expected: vec![Value::Int(1), Value::Int(2), Value::Int(3)],
+                is_err: false,
+            },
+            Case {
+                description: "user-defined override",
+                input: &[": foo dup ;", ": foo dup dup ;", "1 foo"],
+                expected: vec![Value::Int(1), Value::Int(1), Value::Int(1)],
+                is_err: false,
+            },
+            Case {
+                description: "built-in override",
+                input: &[": swap dup ;", "1 swap"],
+                expected: vec![Value::Int(1), Value::Int(1)],
+                is_err: false,
+            },
+            Case {
+                description: "built-in operator override",
+                input: &[": + * ;", "3 4 +"],
+                expected: vec![Value::Int(12)],
+                is_err: false,
+            },
+            Case {
+                description: "no redefinition",
+                input: &[": foo 5 ;", ": bar foo ;", ": foo 6 ;", "bar foo"],
+                expected: vec![Value::Int(5), Value::Int(6)],
+                is_err: false,
+            },
+            Case {
+                description: "reuse in definition",
+                input: &[": foo 10 ;", ": foo foo 1 + ;", "foo"],
+                expected: vec![Value::Int(11)],
+                is_err: false,
+            },
+            Case {
+                description: "redefine numbers",
+                input: &[": 1 2 ;"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "non-existent word",
+                input: &["foo"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "DUP case insensitivity",
+                input: &["1 DUP Dup dup"],
+                expected: vec![Value::Int(1), Value::Int(1), Value::Int(1), Value::Int(1)],
+                is_err: false,
+            },
+            Case {
+                description: "DROP case insensitivity",
+                input: &["1 2 3 4 DROP Drop drop"],
+                expected: vec![Value::Int(1)],
+                is_err: false,
+            },
+            Case {
+                description: "SWAP case insensitivity",
+                input: &["1 2 SWAP 3 Swap 4 swap"],
+                expected: vec![Value::Int(2), Value::Int(3), Value::Int(4), Value::Int(1)],
+                is_err: false,
+            },
+            Case {
+                description: "OVER case insensitivity",
+                input: &["1 2 OVER Over over"],
+                expected: vec![
+                    Value::Int(1),
+                    Value::Int(2),
+                    Value::Int(1),
+                    Value::Int(2),
+                    Value::Int(1),
+                ],
+                is_err: false,
+            },
+            Case {
+                description: "user-defined case insensitivity",
+                input: &[": foo dup ;", "1 FOO Foo foo"],
+                expected: vec![Value::Int(1), Value::Int(1), Value::Int(1), Value::Int(1)],
+                is_err: false,
+            },
+            Case {
+                description: "definition case insensitivity",
+                input: &[": SWAP DUP Dup dup ;", "1 swap"],
+                expected: vec![Value::Int(1), Value::Int(1), Value::Int(1), Value::Int(1)],
+                is_err: false,
+            },
+            Case {
+                description: "redefine of builtin after define user function on it",
+                input: &[": foo dup ;", ": dup 1 ;", "2 foo"],
+                expected: vec![Value::Int(2), Value::Int(2)],
+                is_err: false,
+            },
+            Case {
+                description: "less than",
+                input: &["1 2 <"],
+                expected: vec![Value::Bool(true)],
+                is_err: false,
+            },
+            Case {
+                description: "greater than false",
+                input: &["1 2 >"],
+                expected: vec![Value::Bool(false)],
+                is_err: false,
+            },
+            Case {
+                description: "equal",
+                input: &["3 3 ="],
+                expected: vec![Value::Bool(true)],
+                is_err: false,
+            },
+            Case {
+                description: "equal across types",
+                input: &[r#"3 s" 3" ="#],
+                expected: vec![Value::Bool(false)],
+                is_err: false,
+            },
+            Case {
+                description: "and or invert",
+                input: &["true false and true false or false invert"],
+                expected: vec![Value::Bool(false), Value::Bool(true), Value::Bool(true)],
+                is_err: false,
+            },
+            Case {
+                description: "if then, true branch",
+                input: &[": abs dup 0 < if -1 * then ;", "-5 abs"],
+                expected: vec![Value::Int(5)],
+                is_err: false,
+            },
+            Case {
+                description: "if then, false branch",
+                input: &[": abs dup 0 < if -1 * then ;", "5 abs"],
+                expected: vec![Value::Int(5)],
+                is_err: false,
+            },
+            Case {
+                description: "if else then",
+                input: &[": max2 over over < if swap drop else drop then ;", "3 7 max2"],
+                expected: vec![Value::Int(7)],
+                is_err: false,
+            },
+            Case {
+                description: "unbalanced if",
+                input: &[": bad 1 if 2 ;"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "unbalanced then",
+                input: &[": bad 1 then ;"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "begin until",
+                input: &[": count5 0 begin 1 + dup 5 = until ;", "count5"],
+                expected: vec![Value::Int(5)],
+                is_err: false,
+            },
+            Case {
+                description: "do loop with i",
+                input: &[": sum5 0 5 0 do i + loop ;", "sum5"],
+                expected: vec![Value::Int(10)],
+                is_err: false,
+            },
+            Case {
+                description: "nested do loop",
+                input: &[": grid 0 3 0 do 3 0 do 1 + loop loop ;", "grid"],
+                expected: vec![Value::Int(9)],
+                is_err: false,
+            },
+            Case {
+                description: "unbalanced until",
+                input: &[": bad 1 until ;"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "unbalanced loop",
+                input: &[": bad 5 0 loop ;"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "i outside loop",
+                input: &[": bad i ;", "bad"],
+                is_err: true,
+                expected: vec![],
+            },
+            Case {
+                description: "rot",
+                input: &["1 2 3 rot"],
+                expected: vec![Value::Int(2), Value::Int(3), Value::Int(1)],
+                is_err: false,
+            },
+            Case {
+                description: "nip",
+                input: &["1 2 nip"],
+                expected: vec![Value::Int(2)],
+                is_err: false,
+            },
+            Case {
+                description: "tuck",
+                input: &["1 2 tuck"],
+                expected: vec![Value::Int(2), Value::Int(1), Value::Int(2)],
+                is_err: false,
+            },
+            Case {
+                description: "2dup",
+                input: &["1 2 2dup"],
+                expected: vec![Value::Int(1), Value::Int(2), Value::Int(1), Value::Int(2)],
+                is_err: false,
+            },
+            Case {
+                description: "negate abs",
+                input: &["5 negate abs"],
+                expected: vec![Value::Int(5)],
+                is_err: false,
+            },
+            Case {
+                description: "mod",
+                input: &["7 3 mod"],
+                expected: vec![Value::Int(1)],
+                is_err: false,
+            },
+            Case {
+                description: "min max",
+                input: &["3 7 min 3 7 max"],
+                expected: vec![Value::Int(3), Value::Int(7)],
+                is_err: false,
+            },
+            Case {
+                description: "prelude 2drop and square",
+                input: &["1 2 2drop 3 square"],
+                expected: vec![Value::Int(9)],
+                is_err: false,
+            },
+            Case {
+                description: "user overrides prelude word",
+                input: &[": square dup ;", "3 square"],
+                expected: vec![Value::Int(3), Value::Int(3)],
+                is_err: false,
+            },
+        ];
+
+        for case in cases {
+            eprintln!("Run test for {}", case.description);
+            let mut ev = Evaluator::new();
+            let resp = || -> Result<Vec<Value>, EvalError> {
+                let mut ret = Vec::new();
+                for row in case.input {
+                    ret = ev.process(row)?;
+                }
+                Ok(ret)
+            }();
+            if case.is_err {
+                assert_eq!(case.is_err, resp.is_err());
+            } else {
+                assert_eq!(case.expected, resp.unwrap());
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use wasm_bindgen::prelude::*;
+
+use crate::Evaluator;
+
+/// A persistent interpreter session for embedding in a browser, mirroring
+/// the REPL's state-carrying behaviour: definitions and the data stack both
+/// survive across calls to `eval`.
+#[wasm_bindgen]
+pub struct Session {
+    ev: Evaluator,
+}
+
+#[wasm_bindgen]
+impl Session {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Session {
+        Session { ev: Evaluator::new() }
+    }
+
+    /// Compile and run one line, returning the stack's new contents
+    /// rendered as a space-separated string, or the error message on
+    /// failure. A failed line leaves the data stack as it was, though
+    /// definitions compiled earlier on that line are kept. Text
+    /// written by `.` isn't included here — read it separately via
+    /// `take_output`, since `println!` is a no-op on `wasm32`.
+    pub fn eval(&mut self, line: &str) -> Result<String, String> {
+        let result = self.ev.process(line);
+        result
+            .map(|stack| {
+                stack
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .map_err(|e| e.to_string())
+    }
+
+    /// The current data stack, rendered the same way as `eval`'s result.
+    pub fn stack(&self) -> String {
+        self.ev
+            .stack()
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Drain and return the text written by `.` since the last call to
+    /// `take_output` (or since the session was created).
+    pub fn take_output(&mut self) -> String {
+        self.ev.take_output()
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}